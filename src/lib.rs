@@ -2,8 +2,81 @@ use colored::Colorize;
 use regex::Regex;
 use std::error::Error;
 use std::fs;
+use std::io::IsTerminal;
+use std::path::Path;
+use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Mutex};
+use std::thread;
+use std::time::Instant;
 use walkdir::WalkDir;
 
+/// Which colors and text styles to use when `Config::color` is enabled,
+/// populated from repeatable `--colors type:attribute:value` specs.
+#[derive(Clone)]
+pub struct ColorSpecs {
+    pub match_fg: String,
+    pub match_style: Option<String>,
+    pub path_fg: String,
+    pub line_fg: String,
+    pub separator_fg: String,
+}
+
+impl Default for ColorSpecs {
+    fn default() -> Self {
+        ColorSpecs {
+            match_fg: "red".to_string(),
+            match_style: Some("bold".to_string()),
+            path_fg: "purple".to_string(),
+            line_fg: "green".to_string(),
+            separator_fg: "cyan".to_string(),
+        }
+    }
+}
+
+impl ColorSpecs {
+    fn apply_spec(&mut self, spec: &str) -> Result<(), &'static str> {
+        let mut parts = spec.splitn(3, ':');
+        let (type_, attr, value) = match (parts.next(), parts.next(), parts.next()) {
+            (Some(t), Some(a), Some(v)) => (t, a, v),
+            _ => return Err("Invalid color spec, expected type:attribute:value"),
+        };
+        match (type_, attr) {
+            ("match", "fg") => self.match_fg = value.to_string(),
+            ("match", "style") => self.match_style = Some(value.to_string()),
+            ("path", "fg") => self.path_fg = value.to_string(),
+            ("line", "fg") => self.line_fg = value.to_string(),
+            ("separator", "fg") => self.separator_fg = value.to_string(),
+            _ => return Err("Unknown color spec type or attribute"),
+        }
+        Ok(())
+    }
+}
+
+fn colorize(text: &str, fg: &str, style: Option<&str>) -> String {
+    let mut colored = match fg {
+        "black" => text.black(),
+        "red" => text.red(),
+        "green" => text.green(),
+        "yellow" => text.yellow(),
+        "blue" => text.blue(),
+        "magenta" | "purple" => text.purple(),
+        "cyan" => text.cyan(),
+        "white" => text.white(),
+        _ => text.normal(),
+    };
+    if let Some(style) = style {
+        colored = match style {
+            "bold" => colored.bold(),
+            "underline" => colored.underline(),
+            "italic" => colored.italic(),
+            "dimmed" => colored.dimmed(),
+            _ => colored,
+        };
+    }
+    colored.to_string()
+}
+
 pub struct Config {
     pub query: String,
     pub file_path: String,
@@ -12,13 +85,24 @@ pub struct Config {
     pub invert_match: bool,
     pub word_regexp: bool,
     pub line_regexp: bool,
+    pub fixed_strings: bool,
+    pub smart_case: bool,
     // General Output Control
     pub count_matches: bool,
     pub color: bool,
+    pub colors: ColorSpecs,
+    pub json: bool,
     // Output Line Prefix Control
     pub line_number: bool,
     // File and Directory Selection
     pub recursive: bool,
+    pub globs: Vec<String>,
+    pub threads: usize,
+    pub exec: Option<String>,
+    // Binary and Encoding Handling
+    pub text: bool,
+    pub encoding: String,
+    pub stats: bool,
     // Context Line Control
     pub after_context: usize,
     pub before_context: usize,
@@ -34,10 +118,20 @@ impl Config {
         let mut invert_match = false;
         let mut word_regexp = false;
         let mut line_regexp = false;
+        let mut fixed_strings = false;
+        let mut smart_case = false;
         let mut count_matches = false;
+        let mut json = false;
         let mut line_number = false;
-        let mut color = false;
+        let mut color_choice = "auto".to_string();
+        let mut colors = ColorSpecs::default();
         let mut recursive = false;
+        let mut globs: Vec<String> = Vec::new();
+        let mut threads: Option<usize> = None;
+        let mut exec: Option<String> = None;
+        let mut text = false;
+        let mut encoding = "utf-8".to_string();
+        let mut stats = false;
         let mut query: Option<String> = None;
         let mut file_path: Option<String> = None;
         let mut after_context = 0;
@@ -57,10 +151,29 @@ impl Config {
                 "-v" | "--invert-match" => invert_match = true,
                 "-w" | "--word-regexp" => word_regexp = true,
                 "-x" | "--line-regexp" => line_regexp = true,
+                "-F" | "--fixed-strings" => fixed_strings = true,
+                "-S" | "--smart-case" => smart_case = true,
                 "-c" | "--count" => count_matches = true,
-                "--color" => color = true,
+                "--json" => json = true,
+                "--exec" => exec = Some(args.next().ok_or("Missing exec command")?),
+                "-a" | "--text" => text = true,
+                "--encoding" => encoding = args.next().ok_or("Missing encoding argument")?,
+                "--stats" => stats = true,
+                "--color" => color_choice = args.next().ok_or("Missing color mode argument")?,
+                "--colors" => {
+                    let spec = args.next().ok_or("Missing color spec argument")?;
+                    colors.apply_spec(&spec)?;
+                }
                 "-n" | "--line-number" => line_number = true,
                 "-r" | "--recursive" => recursive = true,
+                "-g" | "--glob" => globs.push(args.next().ok_or("Missing glob pattern")?),
+                "-j" | "--threads" => {
+                    threads = Some(
+                        args.next()
+                            .and_then(|s| s.parse().ok())
+                            .ok_or("Invalid thread count argument")?,
+                    )
+                }
                 "-A" => after_context = args.next().and_then(|s| s.parse().ok()).unwrap_or(0),
                 "-B" => before_context = args.next().and_then(|s| s.parse().ok()).unwrap_or(0),
                 "-C" => {
@@ -109,6 +222,18 @@ impl Config {
         } else {
             file_path.ok_or("Didn't get a file path")?
         };
+        let threads = threads.unwrap_or_else(|| {
+            thread::available_parallelism().map_or(1, |n| n.get())
+        });
+        let no_color_env = std::env::var_os("NO_COLOR").is_some();
+        let color = match color_choice.as_str() {
+            "always" => true,
+            "never" => false,
+            _ => !no_color_env && std::io::stdout().is_terminal(),
+        };
+        // `colored`'s ANSI emission is gated by its own independent
+        // SHOULD_COLORIZE global; force it to agree with our resolved `color`.
+        colored::control::set_override(color);
 
         Ok(Config {
             query,
@@ -116,11 +241,21 @@ impl Config {
             ignore_case,
             line_regexp,
             word_regexp,
+            fixed_strings,
+            smart_case,
             invert_match,
             count_matches,
             line_number,
             color,
+            colors,
+            json,
             recursive,
+            globs,
+            threads,
+            exec,
+            text,
+            encoding,
+            stats,
             after_context,
             before_context,
             group_separator,
@@ -128,8 +263,12 @@ impl Config {
     }
 }
 
-fn build_regex(config: &Config) -> Regex {
-    let mut pattern = regex::escape(&config.query);
+fn build_regex(config: &Config) -> Result<Regex, regex::Error> {
+    let mut pattern = if config.fixed_strings {
+        regex::escape(&config.query)
+    } else {
+        config.query.clone()
+    };
 
     if config.word_regexp {
         pattern = format!(r"\b{}\b", pattern);
@@ -138,41 +277,317 @@ fn build_regex(config: &Config) -> Regex {
         pattern = format!(r"^{}$", pattern);
     }
 
-    let regex_pattern = if config.ignore_case {
+    let ignore_case =
+        config.ignore_case || (config.smart_case && !has_uppercase_literal(&config.query));
+
+    let regex_pattern = if ignore_case {
         format!("(?i){}", pattern)
     } else {
         pattern
     };
 
-    Regex::new(&regex_pattern).unwrap()
+    Regex::new(&regex_pattern)
+}
+
+fn has_uppercase_literal(pattern: &str) -> bool {
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            chars.next();
+            continue;
+        }
+        if c.is_ascii_uppercase() {
+            return true;
+        }
+    }
+    false
+}
+
+fn glob_to_regex(glob: &str) -> Result<Regex, regex::Error> {
+    let mut pattern = String::from("^");
+    // Gitignore-style: a glob with no slash matches the basename at any
+    // depth, not just a file sitting directly in the search root.
+    if !glob.contains('/') {
+        pattern.push_str("(.*/)?");
+    }
+    let mut chars = glob.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '*' => {
+                if chars.peek() == Some(&'*') {
+                    chars.next();
+                    pattern.push_str(".*");
+                } else {
+                    pattern.push_str("[^/]*");
+                }
+            }
+            '?' => pattern.push_str("[^/]"),
+            '\\' | '.' => {
+                pattern.push('\\');
+                pattern.push(c);
+            }
+            _ => pattern.push(c),
+        }
+    }
+
+    pattern.push('$');
+    Regex::new(&pattern)
+}
+
+fn file_has_match(contents: &str, config: &Config, regex: &Regex) -> bool {
+    contents
+        .lines()
+        .any(|line| config.invert_match ^ regex.is_match(line))
+}
+
+const BINARY_SNIFF_LEN: usize = 8 * 1024;
+
+enum FileContents {
+    Text(String),
+    Binary(Vec<u8>),
+}
+
+fn looks_binary(bytes: &[u8]) -> bool {
+    let prefix_len = bytes.len().min(BINARY_SNIFF_LEN);
+    bytes[..prefix_len].contains(&0)
+}
+
+fn decode(bytes: &[u8], encoding: &str) -> String {
+    match encoding {
+        "latin-1" | "latin1" => bytes.iter().map(|&b| b as char).collect(),
+        _ => String::from_utf8_lossy(bytes).into_owned(),
+    }
+}
+
+fn read_file(path: &str, config: &Config) -> Result<(FileContents, usize), Box<dyn Error>> {
+    let bytes = fs::read(path)?;
+    let raw_len = bytes.len();
+    if !config.text && looks_binary(&bytes) {
+        return Ok((FileContents::Binary(bytes), raw_len));
+    }
+    Ok((FileContents::Text(decode(&bytes, &config.encoding)), raw_len))
+}
+
+/// Accumulates `--stats` counters across however many worker threads
+/// are searching files at once.
+#[derive(Default)]
+struct Stats {
+    matched_lines: AtomicUsize,
+    matched_files: AtomicUsize,
+    files_searched: AtomicUsize,
+    bytes_searched: AtomicUsize,
+}
+
+impl Stats {
+    fn record_matched_line(&self) {
+        self.matched_lines.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn record_file(&self, bytes: usize, matched: bool) {
+        self.files_searched.fetch_add(1, Ordering::Relaxed);
+        self.bytes_searched.fetch_add(bytes, Ordering::Relaxed);
+        if matched {
+            self.matched_files.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    fn print_summary(&self, elapsed: std::time::Duration) {
+        println!(
+            "{} matched lines\n{} matched files\n{} files searched\n{} bytes searched\n{:.6} seconds",
+            self.matched_lines.load(Ordering::Relaxed),
+            self.matched_files.load(Ordering::Relaxed),
+            self.files_searched.load(Ordering::Relaxed),
+            self.bytes_searched.load(Ordering::Relaxed),
+            elapsed.as_secs_f64()
+        );
+    }
+}
+
+/// Quotes `s` per POSIX shell rules so it's safe to splice into a `sh -c`
+/// command string, even if it contains spaces or shell metacharacters.
+fn shell_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
+
+fn expand_exec_template(template: &str, path: &str) -> String {
+    let path_ref = Path::new(path);
+    let basename = path_ref
+        .file_name()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.to_string());
+    let without_ext = match path_ref.extension() {
+        Some(ext) => path
+            .strip_suffix(&format!(".{}", ext.to_string_lossy()))
+            .unwrap_or(path)
+            .to_string(),
+        None => path.to_string(),
+    };
+
+    template
+        .replace("{}", &shell_quote(path))
+        .replace("{/}", &shell_quote(&basename))
+        .replace("{.}", &shell_quote(&without_ext))
+}
+
+fn run_exec(template: &str, path: &str) -> Result<(), Box<dyn Error>> {
+    let command_str = expand_exec_template(template, path);
+
+    let status = Command::new("sh").arg("-c").arg(&command_str).status()?;
+    if !status.success() {
+        return Err(format!("command exited with {}: {}", status, command_str).into());
+    }
+    Ok(())
 }
 
 pub fn run(config: &Config) -> Result<(), Box<dyn Error>> {
-    let regex = build_regex(config);
-    let mut needs_separator = false;
+    let start_time = Instant::now();
+    let regex = build_regex(config)?;
+    let stats = Stats::default();
 
     if config.recursive {
-        for entry in WalkDir::new(&config.file_path)
-            .into_iter()
-            .filter_map(|e| e.ok())
-        {
-            if entry.file_type().is_file() {
-                let path = entry.path().display().to_string();
-                if let Ok(contents) = fs::read_to_string(&path) {
-                    let results = search(&contents, config, &regex, &path, &mut needs_separator);
-                    for line in results {
+        let mut include_globs = Vec::new();
+        let mut exclude_globs = Vec::new();
+        for glob in &config.globs {
+            if let Some(pattern) = glob.strip_prefix('!') {
+                exclude_globs.push(glob_to_regex(pattern)?);
+            } else {
+                include_globs.push(glob_to_regex(glob)?);
+            }
+        }
+
+        let (work_tx, work_rx) = mpsc::channel::<String>();
+        let work_rx = Mutex::new(work_rx);
+        let (result_tx, result_rx) = mpsc::channel::<Vec<String>>();
+
+        thread::scope(|scope| {
+            let printer = scope.spawn(move || {
+                for lines in result_rx {
+                    for line in lines {
                         println!("{}", line);
                     }
                 }
+            });
+
+            for _ in 0..config.threads.max(1) {
+                let work_rx = &work_rx;
+                let result_tx = result_tx.clone();
+                let regex = regex.clone();
+                let stats = &stats;
+                scope.spawn(move || {
+                    while let Ok(path) = work_rx.lock().unwrap().recv() {
+                        let mut needs_separator = false;
+                        let (contents, raw_len) = match read_file(&path, config) {
+                            Ok((FileContents::Text(contents), raw_len)) => (contents, raw_len),
+                            Ok((FileContents::Binary(bytes), raw_len)) => {
+                                let matched = config.exec.is_none()
+                                    && file_has_match(&decode(&bytes, "utf-8"), config, &regex);
+                                stats.record_file(raw_len, matched);
+                                if matched {
+                                    let _ =
+                                        result_tx.send(vec![format!("Binary file {} matches", path)]);
+                                }
+                                continue;
+                            }
+                            Err(_) => continue,
+                        };
+
+                        if let Some(template) = &config.exec {
+                            let matched = file_has_match(&contents, config, &regex);
+                            stats.record_file(raw_len, matched);
+                            if matched {
+                                if let Err(e) = run_exec(template, &path) {
+                                    let _ = result_tx
+                                        .send(vec![format!("Exec error for {}: {}", path, e)]);
+                                }
+                            }
+                            continue;
+                        }
+                        let matched = file_has_match(&contents, config, &regex);
+                        let results = search(
+                            &contents,
+                            config,
+                            &regex,
+                            &path,
+                            &mut needs_separator,
+                            Some(stats),
+                        );
+                        stats.record_file(raw_len, matched);
+                        if !results.is_empty() {
+                            let _ = result_tx.send(results);
+                        }
+                    }
+                });
             }
-        }
+            drop(result_tx);
+
+            for entry in WalkDir::new(&config.file_path)
+                .into_iter()
+                .filter_map(|e| e.ok())
+            {
+                if entry.file_type().is_file() {
+                    let path = entry.path().display().to_string();
+                    let rel_path = entry
+                        .path()
+                        .strip_prefix(&config.file_path)
+                        .unwrap_or_else(|_| entry.path())
+                        .display()
+                        .to_string();
+
+                    if !include_globs.is_empty()
+                        && !include_globs.iter().any(|g| g.is_match(&rel_path))
+                    {
+                        continue;
+                    }
+                    if exclude_globs.iter().any(|g| g.is_match(&rel_path)) {
+                        continue;
+                    }
+
+                    let _ = work_tx.send(path);
+                }
+            }
+            drop(work_tx);
+
+            printer.join().unwrap();
+        });
     } else {
-        let contents = fs::read_to_string(&config.file_path)?;
-        let results = search(&contents, config, &regex, &"".to_string(), &mut false);
-        for line in results {
-            println!("{}", line);
+        match read_file(&config.file_path, config)? {
+            (FileContents::Text(contents), raw_len) => {
+                if let Some(template) = &config.exec {
+                    let matched = file_has_match(&contents, config, &regex);
+                    stats.record_file(raw_len, matched);
+                    if matched {
+                        run_exec(template, &config.file_path)?;
+                    }
+                } else {
+                    let matched = file_has_match(&contents, config, &regex);
+                    let results = search(
+                        &contents,
+                        config,
+                        &regex,
+                        &"".to_string(),
+                        &mut false,
+                        Some(&stats),
+                    );
+                    stats.record_file(raw_len, matched);
+                    for line in results {
+                        println!("{}", line);
+                    }
+                }
+            }
+            (FileContents::Binary(bytes), raw_len) => {
+                let matched = file_has_match(&decode(&bytes, "utf-8"), config, &regex);
+                stats.record_file(raw_len, matched);
+                if matched {
+                    println!("Binary file {} matches", config.file_path);
+                }
+            }
         }
     }
+
+    if config.stats {
+        stats.print_summary(start_time.elapsed());
+    }
     Ok(())
 }
 
@@ -182,6 +597,7 @@ fn search(
     regex: &Regex,
     file_path: &str,
     needs_separator: &mut bool,
+    stats: Option<&Stats>,
 ) -> Vec<String> {
     let mut results = Vec::new();
     let mut match_count = 0;
@@ -191,7 +607,14 @@ fn search(
 
     for (index, line) in lines.iter().enumerate() {
         let is_match = regex.is_match(line);
-        if config.invert_match ^ is_match || after_context_cnt > 0 {
+        let counts_as_match = config.invert_match ^ is_match;
+        if counts_as_match || after_context_cnt > 0 {
+            if counts_as_match {
+                if let Some(stats) = stats {
+                    stats.record_matched_line();
+                }
+            }
+
             if config.count_matches {
                 match_count += 1;
                 continue;
@@ -200,9 +623,14 @@ fn search(
             if *needs_separator
                 && after_context_cnt == 0
                 && (config.after_context > 0 || config.before_context > 0)
+                && !config.json
             {
                 if config.color {
-                    results.push(config.group_separator.cyan().to_string());
+                    results.push(colorize(
+                        &config.group_separator,
+                        &config.colors.separator_fg,
+                        None,
+                    ));
                 } else {
                     results.push(config.group_separator.to_string());
                 }
@@ -213,46 +641,120 @@ fn search(
                 let start = index
                     .saturating_sub(config.before_context)
                     .max(last_match_index + config.after_context);
-                if start <= (last_match_index + config.after_context) {
+                if start <= (last_match_index + config.after_context) && !config.json {
                     results.pop(); // Remove previous separator
                 }
                 for i in start..index {
-                    results.push(format_line(i, &lines[i], config, &file_path));
+                    if config.json {
+                        results.push(format_json_line(i, lines[i], file_path, regex, "context"));
+                    } else {
+                        results.push(format_line(i, &lines[i], config, &file_path));
+                    }
                 }
             }
 
-            if config.invert_match ^ is_match {
+            if counts_as_match {
                 after_context_cnt = config.after_context;
             } else {
                 after_context_cnt = after_context_cnt.saturating_sub(1);
             }
 
-            let mut fmt_line = line.to_string();
-            if config.color {
-                fmt_line = regex
-                    .replace_all(line, |caps: &regex::Captures| {
-                        caps[0].red().bold().to_string()
-                    })
-                    .to_string();
+            if config.json {
+                let type_tag = if counts_as_match { "match" } else { "context" };
+                results.push(format_json_line(index, line, file_path, regex, type_tag));
+            } else {
+                let mut fmt_line = line.to_string();
+                if config.color {
+                    fmt_line = regex
+                        .replace_all(line, |caps: &regex::Captures| {
+                            colorize(
+                                &caps[0],
+                                &config.colors.match_fg,
+                                config.colors.match_style.as_deref(),
+                            )
+                        })
+                        .to_string();
+                }
+                results.push(format_line(index, &fmt_line, config, &file_path));
             }
-            results.push(format_line(index, &fmt_line, config, &file_path));
 
             last_match_index = index;
         }
     }
 
     if config.count_matches {
-        vec![match_count.to_string()]
+        if config.json {
+            vec![format_json_summary(file_path, match_count)]
+        } else {
+            vec![match_count.to_string()]
+        }
     } else {
         results
     }
 }
 
+fn json_escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn json_path(file_path: &str) -> String {
+    if file_path.is_empty() {
+        "null".to_string()
+    } else {
+        format!("\"{}\"", json_escape(file_path))
+    }
+}
+
+fn format_json_line(
+    index: usize,
+    line: &str,
+    file_path: &str,
+    regex: &Regex,
+    type_tag: &str,
+) -> String {
+    let spans: Vec<String> = regex
+        .find_iter(line)
+        .map(|m| format!("{{\"start\":{},\"end\":{}}}", m.start(), m.end()))
+        .collect();
+    format!(
+        "{{\"type\":\"{}\",\"path\":{},\"line_number\":{},\"line\":\"{}\",\"matches\":[{}]}}",
+        type_tag,
+        json_path(file_path),
+        index + 1,
+        json_escape(line),
+        spans.join(",")
+    )
+}
+
+fn format_json_summary(file_path: &str, match_count: usize) -> String {
+    format!(
+        "{{\"type\":\"summary\",\"path\":{},\"matches\":{}}}",
+        json_path(file_path),
+        match_count
+    )
+}
+
 fn format_line(index: usize, line: &str, config: &Config, file_path: &str) -> String {
     let mut fmt_line = "".to_string();
     if config.recursive {
         if config.color {
-            fmt_line = format!("{}{}", file_path.purple(), ":".cyan());
+            fmt_line = format!(
+                "{}{}",
+                colorize(file_path, &config.colors.path_fg, None),
+                colorize(":", &config.colors.separator_fg, None)
+            );
         } else {
             fmt_line = format!("{}:", file_path);
         }
@@ -262,8 +764,8 @@ fn format_line(index: usize, line: &str, config: &Config, file_path: &str) -> St
             fmt_line = format!(
                 "{}{}{}{}",
                 fmt_line,
-                (index + 1).to_string().green(),
-                ":".cyan(),
+                colorize(&(index + 1).to_string(), &config.colors.line_fg, None),
+                colorize(":", &config.colors.separator_fg, None),
                 line
             );
         } else {
@@ -286,11 +788,21 @@ mod tests {
             ignore_case: false,
             line_regexp: false,
             word_regexp: false,
+            fixed_strings: false,
+            smart_case: false,
             invert_match: false,
             count_matches: false,
             line_number: false,
             color: false,
+            colors: ColorSpecs::default(),
+            json: false,
             recursive: false,
+            globs: Vec::new(),
+            threads: 1,
+            exec: None,
+            text: false,
+            encoding: "utf-8".to_string(),
+            stats: false,
             after_context: 0,
             before_context: 0,
             group_separator: "--".to_string(),
@@ -306,9 +818,10 @@ mod tests {
         let results = search(
             contents,
             &config,
-            &build_regex(&config),
+            &build_regex(&config).unwrap(),
             &"".to_string(),
             &mut false,
+            None,
         );
         assert_eq!(results, vec!["safe, fast, productive.".to_string()]);
     }
@@ -322,9 +835,10 @@ mod tests {
         let results = search(
             contents,
             &config,
-            &build_regex(&config),
+            &build_regex(&config).unwrap(),
             &"".to_string(),
             &mut false,
+            None,
         );
         assert_eq!(results, vec!["Rust:".to_string(), "Trust me.".to_string()]);
     }
@@ -338,9 +852,10 @@ mod tests {
         let results = search(
             contents,
             &config,
-            &build_regex(&config),
+            &build_regex(&config).unwrap(),
             &"".to_string(),
             &mut false,
+            None,
         );
         assert_eq!(results, vec!["Me me".to_string(), "me.".to_string()]);
     }
@@ -354,10 +869,169 @@ mod tests {
         let results = search(
             contents,
             &config,
-            &build_regex(&config),
+            &build_regex(&config).unwrap(),
             &"".to_string(),
             &mut false,
+            None,
         );
         assert_eq!(results, vec!["Rusty".to_string()]);
     }
+
+    #[test]
+    fn smart_case_lowercase_query_ignores_case() {
+        let mut config = base_config();
+        config.query = "rust".to_string();
+        config.smart_case = true;
+        let contents = "Rust:\nTrust me.";
+        let results = search(
+            contents,
+            &config,
+            &build_regex(&config).unwrap(),
+            &"".to_string(),
+            &mut false,
+            None,
+        );
+        assert_eq!(results, vec!["Rust:".to_string(), "Trust me.".to_string()]);
+    }
+
+    #[test]
+    fn smart_case_uppercase_query_stays_case_sensitive() {
+        let mut config = base_config();
+        config.query = "Rust".to_string();
+        config.smart_case = true;
+        let contents = "Rust:\ntrust me.";
+        let results = search(
+            contents,
+            &config,
+            &build_regex(&config).unwrap(),
+            &"".to_string(),
+            &mut false,
+            None,
+        );
+        assert_eq!(results, vec!["Rust:".to_string()]);
+    }
+
+    #[test]
+    fn glob_without_slash_matches_any_directory_depth() {
+        let re = glob_to_regex("*.rs").unwrap();
+        assert!(re.is_match("main.rs"));
+        assert!(re.is_match("src/main.rs"));
+        assert!(!re.is_match("src/main.rs.bak"));
+    }
+
+    #[test]
+    fn glob_double_star_crosses_directories() {
+        let re = glob_to_regex("src/**/*.rs").unwrap();
+        assert!(re.is_match("src/a/b/main.rs"));
+        assert!(!re.is_match("tests/main.rs"));
+    }
+
+    #[test]
+    fn json_mode_emits_match_object_with_spans() {
+        let mut config = base_config();
+        config.query = "fast".to_string();
+        config.json = true;
+        let contents = "safe, fast, productive.";
+        let results = search(
+            contents,
+            &config,
+            &build_regex(&config).unwrap(),
+            &"".to_string(),
+            &mut false,
+            None,
+        );
+        assert_eq!(
+            results,
+            vec![
+                r#"{"type":"match","path":null,"line_number":1,"line":"safe, fast, productive.","matches":[{"start":6,"end":10}]}"#
+                    .to_string()
+            ]
+        );
+    }
+
+    #[test]
+    fn json_mode_count_emits_summary_object() {
+        let mut config = base_config();
+        config.query = "fast".to_string();
+        config.json = true;
+        config.count_matches = true;
+        let contents = "safe, fast, productive.\nsafe and fast.";
+        let results = search(
+            contents,
+            &config,
+            &build_regex(&config).unwrap(),
+            &"".to_string(),
+            &mut false,
+            None,
+        );
+        assert_eq!(
+            results,
+            vec![r#"{"type":"summary","path":null,"matches":2}"#.to_string()]
+        );
+    }
+
+    #[test]
+    fn exec_template_expands_placeholders() {
+        let expanded = expand_exec_template("wc -l {} {/} {.}", "src/main.rs");
+        assert_eq!(expanded, "wc -l 'src/main.rs' 'main.rs' 'src/main'");
+    }
+
+    #[test]
+    fn exec_template_quotes_metacharacters_in_path() {
+        let expanded = expand_exec_template("wc -l {}", "has space/it's a file.txt");
+        assert_eq!(expanded, r"wc -l 'has space/it'\''s a file.txt'");
+    }
+
+    #[test]
+    fn color_spec_overrides_default() {
+        let mut colors = ColorSpecs::default();
+        colors.apply_spec("match:fg:green").unwrap();
+        colors.apply_spec("match:style:underline").unwrap();
+        assert_eq!(colors.match_fg, "green");
+        assert_eq!(colors.match_style.as_deref(), Some("underline"));
+    }
+
+    #[test]
+    fn color_spec_rejects_malformed_input() {
+        let mut colors = ColorSpecs::default();
+        assert!(colors.apply_spec("match-fg-green").is_err());
+        assert!(colors.apply_spec("bogus:fg:green").is_err());
+    }
+
+    #[test]
+    fn looks_binary_detects_nul_byte() {
+        assert!(looks_binary(b"safe, \0fast, productive."));
+        assert!(!looks_binary(b"safe, fast, productive."));
+    }
+
+    #[test]
+    fn decode_latin1_maps_bytes_to_chars() {
+        let bytes = [0x66, 0x61, 0x73, 0x74, 0xe9]; // "fast" + e-acute (Latin-1)
+        assert_eq!(decode(&bytes, "latin-1"), "fast\u{e9}");
+    }
+
+    #[test]
+    fn stats_record_file_counts_matched_files_only_when_matched() {
+        let stats = Stats::default();
+        stats.record_file(10, true);
+        stats.record_file(10, false);
+        assert_eq!(stats.files_searched.load(Ordering::Relaxed), 2);
+        assert_eq!(stats.matched_files.load(Ordering::Relaxed), 1);
+        assert_eq!(stats.bytes_searched.load(Ordering::Relaxed), 20);
+    }
+
+    #[test]
+    fn count_mode_matched_file_reflects_actual_match_count() {
+        // A -c/--count search always returns a one-element result vec, even
+        // when nothing matched; the caller must use file_has_match (or the
+        // actual match count), not result-vec emptiness, to decide "matched".
+        let mut config = base_config();
+        config.query = "missing".to_string();
+        config.count_matches = true;
+        let contents = "safe, fast, productive.";
+        let regex = build_regex(&config).unwrap();
+        let results = search(contents, &config, &regex, &"".to_string(), &mut false, None);
+        assert_eq!(results, vec!["0".to_string()]);
+        assert!(!file_has_match(contents, &config, &regex));
+    }
 }